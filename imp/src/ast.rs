@@ -4,29 +4,46 @@ use string_interner::symbol::SymbolU16;
 
 pub type Symbol = SymbolU16;
 pub type Interner = StringInterner<StringBackend<Symbol>>;
+pub type Label = usize;
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub struct ProgramAST {
     pub funcs: Vec<FunctionAST>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub struct FunctionAST {
     pub name: Symbol,
     pub params: Vec<Symbol>,
     pub body: StatementAST,
 }
 
-#[derive(Debug)]
-pub enum StatementAST {
+#[derive(Debug, PartialEq)]
+pub struct StatementAST {
+    // Pre-order index of the statement across the whole program, assigned
+    // once the program has been parsed.
+    pub label: Label,
+    pub kind: StatementKind,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum StatementKind {
     Block(Vec<StatementAST>),
     Assign(Symbol, ExpressionAST),
     IfElse(ExpressionAST, Box<StatementAST>, Option<Box<StatementAST>>),
     While(ExpressionAST, Box<StatementAST>),
+    For(
+        Box<StatementAST>,
+        ExpressionAST,
+        Box<StatementAST>,
+        Box<StatementAST>,
+    ),
+    Break,
+    Continue,
     Return(ExpressionAST),
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub enum ExpressionAST {
     NumberLiteral(i32),
     Variable(Symbol),
@@ -45,4 +62,202 @@ pub enum ExpressionAST {
     LessEquals(Box<ExpressionAST>, Box<ExpressionAST>),
     Greater(Box<ExpressionAST>, Box<ExpressionAST>),
     GreaterEquals(Box<ExpressionAST>, Box<ExpressionAST>),
+
+    And(Box<ExpressionAST>, Box<ExpressionAST>),
+    Or(Box<ExpressionAST>, Box<ExpressionAST>),
+    Not(Box<ExpressionAST>),
+}
+
+impl ProgramAST {
+    pub(crate) fn label_statements(&mut self) {
+        let mut next = 0;
+        for func in &mut self.funcs {
+            func.body.label_pre_order(&mut next);
+        }
+    }
+}
+
+impl StatementAST {
+    pub(crate) fn unlabeled(kind: StatementKind) -> StatementAST {
+        StatementAST { label: 0, kind }
+    }
+
+    fn label_pre_order(&mut self, next: &mut Label) {
+        use StatementKind::*;
+        self.label = *next;
+        *next += 1;
+        match &mut self.kind {
+            Block(stmts) => {
+                for stmt in stmts {
+                    stmt.label_pre_order(next);
+                }
+            }
+            IfElse(_, ts, fs) => {
+                ts.label_pre_order(next);
+                if let Some(fs) = fs {
+                    fs.label_pre_order(next);
+                }
+            }
+            While(_, body) => body.label_pre_order(next),
+            For(init, _, step, body) => {
+                init.label_pre_order(next);
+                step.label_pre_order(next);
+                body.label_pre_order(next);
+            }
+            Assign(..) | Break | Continue | Return(..) => {}
+        }
+    }
+
+    pub(crate) fn has_stray_loop_control(&self) -> bool {
+        use StatementKind::*;
+        match &self.kind {
+            Block(stmts) => stmts.iter().any(Self::has_stray_loop_control),
+            IfElse(_, ts, fs) => {
+                ts.has_stray_loop_control()
+                    || fs.as_ref().is_some_and(|fs| fs.has_stray_loop_control())
+            }
+            Break | Continue => true,
+            Assign(..) | While(..) | For(..) | Return(..) => false,
+        }
+    }
+}
+
+impl ExpressionAST {
+    pub fn const_fold(self) -> ExpressionAST {
+        use ExpressionAST::*;
+
+        fn fold<O, F>(lhs: ExpressionAST, rhs: ExpressionAST, op: O, f: F) -> ExpressionAST
+        where
+            O: FnOnce(Box<ExpressionAST>, Box<ExpressionAST>) -> ExpressionAST,
+            F: FnOnce(i32, i32) -> Option<i32>,
+        {
+            let lhs = lhs.const_fold();
+            let rhs = rhs.const_fold();
+            if let (NumberLiteral(lhs), NumberLiteral(rhs)) = (&lhs, &rhs)
+                && let Some(folded) = f(*lhs, *rhs)
+            {
+                return NumberLiteral(folded);
+            }
+            op(Box::new(lhs), Box::new(rhs))
+        }
+
+        match self {
+            NumberLiteral(_) | Variable(_) => self,
+            Call(func, args) => Call(func, args.into_iter().map(Self::const_fold).collect()),
+
+            Add(lhs, rhs) => fold(*lhs, *rhs, Add, |a, b| Some(a.wrapping_add(b))),
+            Subtract(lhs, rhs) => fold(*lhs, *rhs, Subtract, |a, b| Some(a.wrapping_sub(b))),
+            Multiply(lhs, rhs) => fold(*lhs, *rhs, Multiply, |a, b| Some(a.wrapping_mul(b))),
+            // Division and modulo by zero are left unfolded so that analyses
+            // still see the original operation.
+            Divide(lhs, rhs) => fold(*lhs, *rhs, Divide, |a, b| {
+                (b != 0).then(|| a.wrapping_div(b))
+            }),
+            Modulo(lhs, rhs) => fold(*lhs, *rhs, Modulo, |a, b| {
+                (b != 0).then(|| a.wrapping_rem(b))
+            }),
+
+            EqualsEquals(lhs, rhs) => fold(*lhs, *rhs, EqualsEquals, |a, b| Some((a == b) as i32)),
+            NotEquals(lhs, rhs) => fold(*lhs, *rhs, NotEquals, |a, b| Some((a != b) as i32)),
+            Less(lhs, rhs) => fold(*lhs, *rhs, Less, |a, b| Some((a < b) as i32)),
+            LessEquals(lhs, rhs) => fold(*lhs, *rhs, LessEquals, |a, b| Some((a <= b) as i32)),
+            Greater(lhs, rhs) => fold(*lhs, *rhs, Greater, |a, b| Some((a > b) as i32)),
+            GreaterEquals(lhs, rhs) => {
+                fold(*lhs, *rhs, GreaterEquals, |a, b| Some((a >= b) as i32))
+            }
+
+            // Only literal-only operands are folded, so `0 && y` is kept intact
+            // even though short-circuiting would never evaluate `y`.
+            And(lhs, rhs) => fold(*lhs, *rhs, And, |a, b| Some((a != 0 && b != 0) as i32)),
+            Or(lhs, rhs) => fold(*lhs, *rhs, Or, |a, b| Some((a != 0 || b != 0) as i32)),
+            Not(expr) => match expr.const_fold() {
+                NumberLiteral(a) => NumberLiteral((a == 0) as i32),
+                expr => Not(Box::new(expr)),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ExpressionAST::*;
+    use super::*;
+
+    fn num(n: i32) -> Box<ExpressionAST> {
+        Box::new(NumberLiteral(n))
+    }
+
+    #[test]
+    fn fold_literals() {
+        let mut interner = Interner::new();
+        let x = interner.get_or_intern("x");
+
+        assert_eq!(Add(num(10), num(5)).const_fold(), NumberLiteral(15));
+        assert_eq!(
+            Add(Box::new(Variable(x)), Box::new(Multiply(num(2), num(3)))).const_fold(),
+            Add(Box::new(Variable(x)), num(6))
+        );
+        assert_eq!(
+            Call(x, vec![Subtract(num(7), num(2))]).const_fold(),
+            Call(x, vec![NumberLiteral(5)])
+        );
+    }
+
+    #[test]
+    fn fold_wraps() {
+        assert_eq!(
+            Add(num(i32::MAX), num(1)).const_fold(),
+            NumberLiteral(i32::MIN)
+        );
+        assert_eq!(
+            Divide(num(i32::MIN), num(-1)).const_fold(),
+            NumberLiteral(i32::MIN)
+        );
+        assert_eq!(
+            Modulo(num(i32::MIN), num(-1)).const_fold(),
+            NumberLiteral(0)
+        );
+    }
+
+    #[test]
+    fn fold_division_by_zero() {
+        let mut interner = Interner::new();
+        let x = interner.get_or_intern("x");
+
+        assert_eq!(Divide(num(1), num(0)).const_fold(), Divide(num(1), num(0)));
+        assert_eq!(
+            Modulo(Box::new(Variable(x)), Box::new(Subtract(num(3), num(3)))).const_fold(),
+            Modulo(Box::new(Variable(x)), num(0))
+        );
+    }
+
+    #[test]
+    fn fold_comparisons() {
+        assert_eq!(EqualsEquals(num(3), num(3)).const_fold(), NumberLiteral(1));
+        assert_eq!(NotEquals(num(3), num(3)).const_fold(), NumberLiteral(0));
+        assert_eq!(Less(num(2), num(3)).const_fold(), NumberLiteral(1));
+        assert_eq!(LessEquals(num(4), num(3)).const_fold(), NumberLiteral(0));
+        assert_eq!(Greater(num(-1), num(3)).const_fold(), NumberLiteral(0));
+        assert_eq!(GreaterEquals(num(3), num(3)).const_fold(), NumberLiteral(1));
+    }
+
+    #[test]
+    fn fold_boolean() {
+        let mut interner = Interner::new();
+        let y = interner.get_or_intern("y");
+
+        assert_eq!(And(num(5), num(7)).const_fold(), NumberLiteral(1));
+        assert_eq!(And(num(5), num(0)).const_fold(), NumberLiteral(0));
+        assert_eq!(Or(num(0), num(0)).const_fold(), NumberLiteral(0));
+        assert_eq!(
+            Or(Box::new(Not(Box::new(Less(num(1), num(2))))), num(3)).const_fold(),
+            NumberLiteral(1)
+        );
+        assert_eq!(Not(num(0)).const_fold(), NumberLiteral(1));
+        assert_eq!(Not(num(-4)).const_fold(), NumberLiteral(0));
+        assert_eq!(
+            And(num(0), Box::new(Variable(y))).const_fold(),
+            And(num(0), Box::new(Variable(y)))
+        );
+    }
 }
@@ -3,3 +3,187 @@ use lalrpop_util::lalrpop_mod;
 pub mod ast;
 
 lalrpop_mod!(pub grammar);
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::ExpressionAST::*;
+    use crate::ast::StatementKind::*;
+    use crate::ast::*;
+    use crate::grammar::ProgramParser;
+    use lalrpop_util::ParseError;
+
+    fn parse(interner: &mut Interner, src: &str) -> Option<ProgramAST> {
+        ProgramParser::new().parse(interner, src).ok()
+    }
+
+    fn body(interner: &mut Interner, src: &str) -> Vec<StatementAST> {
+        let mut program = parse(interner, &format!("fn f() {{ {src} }}")).unwrap();
+        match program.funcs.remove(0).body.kind {
+            Block(stmts) => stmts,
+            _ => panic!(),
+        }
+    }
+
+    fn expr(interner: &mut Interner, src: &str) -> ExpressionAST {
+        match body(interner, &format!("return {src};")).remove(0).kind {
+            Return(e) => e,
+            _ => panic!(),
+        }
+    }
+
+    fn stmt(label: Label, kind: StatementKind) -> StatementAST {
+        StatementAST { label, kind }
+    }
+
+    #[test]
+    fn parse_for() {
+        let mut interner = Interner::new();
+        let stmts = body(&mut interner, "for (i = 0; i < 10; i = i + 1) { x = i; }");
+        let i = interner.get("i").unwrap();
+        let x = interner.get("x").unwrap();
+        let one = Box::new(NumberLiteral(1));
+        assert_eq!(
+            stmts,
+            vec![stmt(
+                1,
+                For(
+                    Box::new(stmt(2, Assign(i, NumberLiteral(0)))),
+                    Less(Box::new(Variable(i)), Box::new(NumberLiteral(10))),
+                    Box::new(stmt(3, Assign(i, Add(Box::new(Variable(i)), one)))),
+                    Box::new(stmt(4, Block(vec![stmt(5, Assign(x, Variable(i)))]))),
+                )
+            )]
+        );
+    }
+
+    #[test]
+    fn parse_break_continue() {
+        let mut interner = Interner::new();
+        let stmts = body(&mut interner, "while 1 { if 0 { break; } continue; }");
+        let [
+            StatementAST {
+                kind: While(_, loop_body),
+                ..
+            },
+        ] = &stmts[..]
+        else {
+            panic!()
+        };
+        let Block(loop_body) = &loop_body.kind else {
+            panic!()
+        };
+        let then_block = stmt(4, Block(vec![stmt(5, Break)]));
+        assert!(matches!(&loop_body[0].kind, IfElse(_, ts, None) if **ts == then_block));
+        assert_eq!(loop_body[1], stmt(6, Continue));
+
+        let stmts = body(&mut interner, "for (i = 0; 1; i = i + 1) { break; }");
+        let for_body = stmt(4, Block(vec![stmt(5, Break)]));
+        assert!(matches!(&stmts[0].kind, For(_, _, _, s) if **s == for_body));
+    }
+
+    #[test]
+    fn reject_malformed_for() {
+        let mut interner = Interner::new();
+        assert!(parse(&mut interner, "fn f() { for (;;) {} }").is_none());
+        assert!(
+            parse(
+                &mut interner,
+                "fn f() { for (i = 0; i < 1; i = i + 1) i = 2; }"
+            )
+            .is_none()
+        );
+    }
+
+    #[test]
+    fn reject_stray_loop_control() {
+        let mut interner = Interner::new();
+        for src in [
+            "fn f() { break; }",
+            "fn f() { if 1 { continue; } }",
+            "fn f() { if 1 { } else { break; } }",
+        ] {
+            let err = ProgramParser::new().parse(&mut interner, src).unwrap_err();
+            assert!(matches!(err, ParseError::User { .. }));
+        }
+        assert!(parse(&mut interner, "fn f() { while 1 { if 1 { break; } } }").is_some());
+    }
+
+    #[test]
+    fn keyword_prefixed_identifiers() {
+        let mut interner = Interner::new();
+        let stmts = body(&mut interner, "forx = 1; breaker = forx; continued = 2;");
+        let forx = interner.get("forx").unwrap();
+        let breaker = interner.get("breaker").unwrap();
+        let continued = interner.get("continued").unwrap();
+        assert_eq!(
+            stmts,
+            vec![
+                stmt(1, Assign(forx, NumberLiteral(1))),
+                stmt(2, Assign(breaker, Variable(forx))),
+                stmt(3, Assign(continued, NumberLiteral(2))),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_boolean_precedence() {
+        let mut interner = Interner::new();
+        let e = expr(&mut interner, "x > 0 && x < 10");
+        let x_sym = interner.get("x").unwrap();
+        let x = || Box::new(Variable(x_sym));
+        assert_eq!(
+            e,
+            And(
+                Box::new(Greater(x(), Box::new(NumberLiteral(0)))),
+                Box::new(Less(x(), Box::new(NumberLiteral(10)))),
+            )
+        );
+
+        let e = expr(&mut interner, "a || b && c");
+        let [a, b, c] = ["a", "b", "c"].map(|v| Box::new(Variable(interner.get(v).unwrap())));
+        assert_eq!(e, Or(a, Box::new(And(b, c))));
+
+        let e = expr(&mut interner, "!x == 0");
+        assert_eq!(
+            e,
+            EqualsEquals(Box::new(Not(x())), Box::new(NumberLiteral(0)))
+        );
+
+        let e = expr(&mut interner, "!!0");
+        assert_eq!(e, Not(Box::new(Not(Box::new(NumberLiteral(0))))));
+
+        let e = expr(&mut interner, "x != 0");
+        assert_eq!(e, NotEquals(x(), Box::new(NumberLiteral(0))));
+    }
+
+    #[test]
+    fn statement_labels() {
+        let mut interner = Interner::new();
+        let src = "fn f() { x = 1; if x { y = 2; } else { y = 3; } } fn g() { return 0; }";
+        let program = parse(&mut interner, src).unwrap();
+
+        let f = &program.funcs[0].body;
+        let Block(f_stmts) = &f.kind else { panic!() };
+        let IfElse(_, ts, Some(fs)) = &f_stmts[1].kind else {
+            panic!()
+        };
+        let Block(fs_stmts) = &fs.kind else { panic!() };
+        assert_eq!(
+            [
+                f.label,
+                f_stmts[0].label,
+                f_stmts[1].label,
+                ts.label,
+                fs.label,
+                fs_stmts[0].label
+            ],
+            [0, 1, 2, 3, 5, 6]
+        );
+
+        let g = &program.funcs[1].body;
+        let Block(g_stmts) = &g.kind else { panic!() };
+        assert_eq!([g.label, g_stmts[0].label], [7, 8]);
+
+        assert_eq!(parse(&mut interner, src).unwrap(), program);
+    }
+}